@@ -0,0 +1,70 @@
+//! Structured provenance for each issued JIT config.
+//!
+//! Without this the only trace of a provisioning event is the JIT config on
+//! stdout and a human line on stderr. An audit record captures who registered
+//! which board, when, and under what identity, as newline-delimited JSON that
+//! can be shipped to a log pipeline. The token and private key are never part
+//! of a record.
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+/// One provisioning event. Deliberately excludes the JIT token and key.
+#[derive(Debug, Serialize)]
+pub struct AuditRecord<'a> {
+    pub timestamp: String,
+    pub runner_name: &'a str,
+    pub labels: &'a [String],
+    pub stage: &'a str,
+    pub org: &'a str,
+    pub github_app_id: u64,
+    pub installation_id: u64,
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Where audit records are written. A no-op when neither output is requested.
+#[derive(Debug, Clone)]
+pub struct AuditSink {
+    path: Option<PathBuf>,
+    stderr_json: bool,
+}
+
+impl AuditSink {
+    pub fn new(path: Option<PathBuf>, stderr_json: bool) -> Self {
+        Self {
+            path,
+            stderr_json,
+        }
+    }
+
+    /// Serialize and emit `record` to the configured sinks.
+    pub fn emit(&self, record: &AuditRecord) -> Result<()> {
+        if self.path.is_none() && !self.stderr_json {
+            return Ok(());
+        }
+
+        let line = serde_json::to_string(record).context("serializing audit record")?;
+
+        if let Some(path) = &self.path {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("opening audit log {}", path.display()))?;
+            writeln!(file, "{line}")
+                .with_context(|| format!("writing audit log {}", path.display()))?;
+        }
+
+        if self.stderr_json {
+            eprintln!("{line}");
+        }
+
+        Ok(())
+    }
+}
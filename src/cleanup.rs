@@ -0,0 +1,110 @@
+//! `cleanup` subcommand: reclaim orphaned runner registrations.
+//!
+//! When an FPGA board crashes its JIT runner is never unregistered, leaving an
+//! `offline` entry in the org. This lists the org's self-hosted runners, keeps
+//! only the ones this tool registered (recognised via
+//! [`RunnerName::parse_embedded_date`]) that are currently offline, and deletes
+//! them. `--max-age` restricts deletion to registrations at least N days old, and
+//! `--dry-run` prints what would be removed without touching anything.
+
+use anyhow::Result;
+use chrono::prelude::*;
+use serde::Deserialize;
+
+use crate::{AuthArgs, OctocrabWrapper, RunnerName};
+
+#[derive(clap::Args, Debug)]
+pub struct CleanupArgs {
+    #[clap(flatten)]
+    auth: AuthArgs,
+    /// Only remove runners whose embedded date is at least this many days old.
+    #[clap(long, value_name = "DAYS")]
+    max_age: Option<u64>,
+    /// Print the runners that would be removed without deleting them.
+    #[clap(short, long)]
+    dry_run: bool,
+}
+
+/// A single self-hosted runner as returned by the Actions API.
+#[derive(Debug, Deserialize)]
+pub struct Runner {
+    pub id: u64,
+    pub name: String,
+    pub status: String,
+}
+
+/// One page of the `list org self-hosted runners` response.
+#[derive(Debug, Deserialize)]
+struct RunnerPage {
+    total_count: u64,
+    #[serde(default)]
+    runners: Vec<Runner>,
+}
+
+impl OctocrabWrapper {
+    /// List every self-hosted runner registered to the org, following
+    /// pagination.
+    pub async fn list_org_runners(&self) -> Result<Vec<Runner>> {
+        let mut runners = Vec::new();
+        let mut page = 1u32;
+        loop {
+            let route = format!(
+                "/orgs/{}/actions/runners?per_page=100&page={page}",
+                self.github_org_name
+            );
+            let response: RunnerPage = self.octocrab.get(route, None::<&()>).await?;
+            let fetched = response.runners.len();
+            runners.extend(response.runners);
+            if fetched == 0 || runners.len() as u64 >= response.total_count {
+                break;
+            }
+            page += 1;
+        }
+        Ok(runners)
+    }
+
+    /// Delete a self-hosted runner registration by id.
+    pub async fn delete_org_runner(&self, id: u64) -> Result<()> {
+        let route = format!("/orgs/{}/actions/runners/{id}", self.github_org_name);
+        self.octocrab._delete(route, None::<&()>).await?;
+        Ok(())
+    }
+}
+
+pub async fn run(args: CleanupArgs) -> Result<()> {
+    eprintln!("Cleaning up stale runners for stage: {:?}", args.auth.stage);
+
+    let config = args.auth.load_config()?;
+    let github = args.auth.octocrab(&config)?;
+
+    let today = Local::now().date_naive();
+    let runners = github.list_org_runners().await?;
+
+    let stale: Vec<Runner> = runners
+        .into_iter()
+        .filter(|runner| runner.status.eq_ignore_ascii_case("offline"))
+        .filter(|runner| match RunnerName::parse_embedded_date(&runner.name) {
+            Some(date) => match args.max_age {
+                Some(max_age) => (today - date).num_days() >= max_age as i64,
+                None => true,
+            },
+            None => false,
+        })
+        .collect();
+
+    if stale.is_empty() {
+        eprintln!("No stale runners to clean up.");
+        return Ok(());
+    }
+
+    for runner in &stale {
+        if args.dry_run {
+            println!("Would remove offline runner '{}' (id {})", runner.name, runner.id);
+        } else {
+            github.delete_org_runner(runner.id).await?;
+            eprintln!("Removed offline runner '{}' (id {})", runner.name, runner.id);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,242 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+
+use crate::{FpgaTarget, Stage};
+
+mod cfg;
+
+pub use cfg::{Predicate, TargetAttrs};
+
+/// Deserialized form of a `--config` file.
+///
+/// The layout borrows from cargo's `.cargo/config`: a `[stages.<name>]` table
+/// per deployment stage, a `[targets.<name>]` table per FPGA board, and a list
+/// of `[[label_rules]]` whose `when` predicates are evaluated against the
+/// target's attributes (cf. cargo's `target.'cfg(..)'.runner`). When no
+/// `--config` is supplied the compiled-in [`Config::default`] is used, which
+/// reproduces the historical hardcoded values for the `carl` and `prod` stages.
+/// The `staging` stage has no compiled-in identity (its old arm was a
+/// `todo!()`), so `--stage staging` requires a `--config` that defines a
+/// `[stages.staging]` table.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    stages: BTreeMap<String, StageConfig>,
+    #[serde(default)]
+    targets: BTreeMap<String, TargetConfig>,
+    #[serde(default)]
+    label_rules: Vec<LabelRule>,
+}
+
+/// Per-stage GitHub App identity.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StageConfig {
+    pub github_app_id: u64,
+    pub github_installation_id: u64,
+    pub github_org_name: String,
+    /// Default key path for this stage. A `--key-path` on the command line
+    /// takes precedence.
+    #[serde(default)]
+    pub key_path: Option<String>,
+}
+
+/// Per-board runner registration settings.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TargetConfig {
+    #[serde(default = "default_runner_group")]
+    pub runner_group: u64,
+}
+
+/// A single label-resolution rule: when `when` matches the target attributes,
+/// `labels` is unioned into the result set.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LabelRule {
+    pub when: String,
+    pub labels: Vec<String>,
+}
+
+fn default_runner_group() -> u64 {
+    // For Caliptra we only use one runner group.
+    1
+}
+
+impl Config {
+    /// Load and parse a TOML config file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("reading config file {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("parsing config file {}", path.display()))
+    }
+
+    /// Look up the definition for `stage`, erroring if it is not configured.
+    pub fn stage(&self, stage: Stage) -> Result<&StageConfig> {
+        self.stages
+            .get(stage.config_key())
+            .ok_or_else(|| anyhow!("stage '{}' is not defined in the config", stage.config_key()))
+    }
+
+    /// Look up the definition for `target`, erroring if it is not configured.
+    pub fn target(&self, target: FpgaTarget) -> Result<&TargetConfig> {
+        self.targets.get(target.config_key()).ok_or_else(|| {
+            anyhow!(
+                "fpga target '{}' is not defined in the config",
+                target.config_key()
+            )
+        })
+    }
+
+    /// Evaluate every label rule against `attrs` and return the union of the
+    /// labels of the matching rules, preserving first-seen order. Errors with
+    /// the attributes it tried if no rule matches.
+    pub fn resolve_labels(&self, attrs: &TargetAttrs) -> Result<Vec<String>> {
+        let mut labels: Vec<String> = Vec::new();
+        let mut matched = false;
+        for rule in &self.label_rules {
+            let predicate = Predicate::parse(&rule.when)
+                .with_context(|| format!("invalid label rule predicate '{}'", rule.when))?;
+            if predicate.eval(attrs)? {
+                matched = true;
+                for label in &rule.labels {
+                    if !labels.contains(label) {
+                        labels.push(label.clone());
+                    }
+                }
+            }
+        }
+        if !matched {
+            bail!("no label rule matched target attributes {attrs}");
+        }
+        Ok(labels)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        // `staging` is intentionally absent: it never had hardcoded values to
+        // preserve, so it resolves only from a `--config`. See the struct docs.
+        let stages = BTreeMap::from([
+            (
+                Stage::Carl.config_key().to_string(),
+                StageConfig {
+                    github_app_id: 1160975,
+                    github_installation_id: 61798278,
+                    github_org_name: "clundin25-testorg".to_string(),
+                    key_path: None,
+                },
+            ),
+            (
+                Stage::Prod.config_key().to_string(),
+                StageConfig {
+                    github_app_id: 379559,
+                    github_installation_id: 40993215,
+                    github_org_name: "chipsalliance".to_string(),
+                    key_path: None,
+                },
+            ),
+        ]);
+
+        let targets = BTreeMap::from([
+            (
+                FpgaTarget::Zcu104.config_key().to_string(),
+                TargetConfig {
+                    runner_group: default_runner_group(),
+                },
+            ),
+            (
+                FpgaTarget::Zcu104Nightly.config_key().to_string(),
+                TargetConfig {
+                    runner_group: default_runner_group(),
+                },
+            ),
+            (
+                FpgaTarget::Vck190.config_key().to_string(),
+                TargetConfig {
+                    runner_group: default_runner_group(),
+                },
+            ),
+        ]);
+
+        // One rule per historical label, each conditioned on the attributes
+        // that used to be encoded in the `match value` arm.
+        let label_rules = vec![
+            LabelRule {
+                when: "board = \"zcu104\"".to_string(),
+                labels: vec!["caliptra-fpga".to_string()],
+            },
+            LabelRule {
+                when: "all(board = \"zcu104\", nightly)".to_string(),
+                labels: vec!["caliptra-fpga-nightly".to_string()],
+            },
+            LabelRule {
+                when: "all(board = \"vck190\", not(dry_run))".to_string(),
+                labels: vec!["vck190".to_string()],
+            },
+            LabelRule {
+                when: "all(board = \"vck190\", dry_run)".to_string(),
+                labels: vec!["vck190-staging".to_string()],
+            },
+        ];
+
+        Self {
+            stages,
+            targets,
+            label_rules,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attrs(board: &str, nightly: bool, dry_run: bool) -> TargetAttrs {
+        TargetAttrs {
+            board: board.to_string(),
+            nightly,
+            dry_run,
+            location: "kir".to_string(),
+        }
+    }
+
+    /// The default rules must reproduce the old `RunnerLabels::new` match:
+    /// zcu104 -> caliptra-fpga, the nightly variant adds caliptra-fpga-nightly,
+    /// and vck190 picks up the `-staging` postfix under `--dry-run`.
+    #[test]
+    fn default_rules_match_legacy_labels() {
+        let config = Config::default();
+        assert_eq!(
+            config.resolve_labels(&attrs("zcu104", false, false)).unwrap(),
+            vec!["caliptra-fpga"]
+        );
+        assert_eq!(
+            config.resolve_labels(&attrs("zcu104", true, false)).unwrap(),
+            vec!["caliptra-fpga", "caliptra-fpga-nightly"]
+        );
+        assert_eq!(
+            config.resolve_labels(&attrs("vck190", false, false)).unwrap(),
+            vec!["vck190"]
+        );
+        assert_eq!(
+            config.resolve_labels(&attrs("vck190", false, true)).unwrap(),
+            vec!["vck190-staging"]
+        );
+    }
+
+    #[test]
+    fn no_matching_rule_errors() {
+        let config = Config::default();
+        assert!(config.resolve_labels(&attrs("kv260", false, false)).is_err());
+    }
+
+    #[test]
+    fn default_omits_staging_stage() {
+        let config = Config::default();
+        assert!(config.stage(Stage::Carl).is_ok());
+        assert!(config.stage(Stage::Prod).is_ok());
+        assert!(config.stage(Stage::Staging).is_err());
+    }
+}
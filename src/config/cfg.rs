@@ -0,0 +1,300 @@
+//! A tiny `cfg(..)`-style predicate language for label rules.
+//!
+//! The grammar mirrors the subset of rustc/cargo `cfg` expressions that is
+//! useful here:
+//!
+//! ```text
+//! predicate := all( list ) | any( list ) | not( predicate )
+//!            | ident = "string"        // board / location
+//!            | ident                   // boolean flag: nightly / dry_run
+//! list      := [ predicate ( , predicate )* ]   // may be empty
+//! ```
+//!
+//! Predicates are evaluated against a [`TargetAttrs`] snapshot of the board.
+
+use std::fmt;
+
+use anyhow::{bail, Result};
+
+/// The attributes a label rule can match against.
+#[derive(Debug, Clone)]
+pub struct TargetAttrs {
+    /// Board family, e.g. `zcu104` or `vck190`.
+    pub board: String,
+    /// Whether this is a nightly variant of the board.
+    pub nightly: bool,
+    /// Whether the tool was invoked with `--dry-run`.
+    pub dry_run: bool,
+    /// Physical location of the runner, e.g. `kir`.
+    pub location: String,
+}
+
+impl fmt::Display for TargetAttrs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{{ board = \"{}\", nightly = {}, dry_run = {}, location = \"{}\" }}",
+            self.board, self.nightly, self.dry_run, self.location
+        )
+    }
+}
+
+/// A parsed label-rule predicate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Predicate {
+    All(Vec<Predicate>),
+    Any(Vec<Predicate>),
+    Not(Box<Predicate>),
+    /// `key = "value"`; supported keys are `board` and `location`.
+    Eq(String, String),
+    /// A bare boolean flag; supported flags are `nightly` and `dry_run`.
+    Flag(String),
+}
+
+impl Predicate {
+    /// Parse a predicate from its `cfg(..)` string form.
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let predicate = parser.parse_predicate()?;
+        if parser.peek().is_some() {
+            bail!("trailing tokens after predicate");
+        }
+        Ok(predicate)
+    }
+
+    /// Evaluate the predicate against `attrs`.
+    pub fn eval(&self, attrs: &TargetAttrs) -> Result<bool> {
+        match self {
+            Predicate::All(preds) => {
+                for p in preds {
+                    if !p.eval(attrs)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Predicate::Any(preds) => {
+                for p in preds {
+                    if p.eval(attrs)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Predicate::Not(p) => Ok(!p.eval(attrs)?),
+            Predicate::Eq(key, value) => match key.as_str() {
+                "board" => Ok(&attrs.board == value),
+                "location" => Ok(&attrs.location == value),
+                _ => bail!("unknown attribute '{key}' in label rule"),
+            },
+            Predicate::Flag(flag) => match flag.as_str() {
+                "nightly" => Ok(attrs.nightly),
+                "dry_run" => Ok(attrs.dry_run),
+                _ => bail!("unknown flag '{flag}' in label rule"),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Eq,
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            ',' => {
+                chars.next();
+                tokens.push(Token::Comma);
+            }
+            '=' => {
+                chars.next();
+                tokens.push(Token::Eq);
+            }
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(ch) => value.push(ch),
+                        None => bail!("unterminated string literal"),
+                    }
+                }
+                tokens.push(Token::Str(value));
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '-' => {
+                let mut ident = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_alphanumeric() || ch == '_' || ch == '-' {
+                        ident.push(ch);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            _ => bail!("unexpected character '{c}' in predicate"),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        if token.is_some() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<()> {
+        match self.next() {
+            Some(ref token) if token == expected => Ok(()),
+            other => bail!("expected {expected:?}, found {other:?}"),
+        }
+    }
+
+    fn parse_predicate(&mut self) -> Result<Predicate> {
+        let ident = match self.next() {
+            Some(Token::Ident(ident)) => ident,
+            other => bail!("expected an identifier, found {other:?}"),
+        };
+
+        match ident.as_str() {
+            "all" | "any" | "not" => {
+                self.expect(&Token::Open)?;
+                let operands = self.parse_list()?;
+                self.expect(&Token::Close)?;
+                match ident.as_str() {
+                    "all" => Ok(Predicate::All(operands)),
+                    "any" => Ok(Predicate::Any(operands)),
+                    "not" => {
+                        if operands.len() != 1 {
+                            bail!("not() takes exactly one predicate");
+                        }
+                        Ok(Predicate::Not(Box::new(operands.into_iter().next().unwrap())))
+                    }
+                    _ => unreachable!(),
+                }
+            }
+            _ => {
+                // `ident = "value"` or a bare boolean flag.
+                if matches!(self.peek(), Some(Token::Eq)) {
+                    self.next();
+                    match self.next() {
+                        Some(Token::Str(value)) => Ok(Predicate::Eq(ident, value)),
+                        other => bail!("expected a string literal, found {other:?}"),
+                    }
+                } else {
+                    Ok(Predicate::Flag(ident))
+                }
+            }
+        }
+    }
+
+    fn parse_list(&mut self) -> Result<Vec<Predicate>> {
+        let mut operands = Vec::new();
+        if matches!(self.peek(), Some(Token::Close)) {
+            return Ok(operands);
+        }
+        loop {
+            operands.push(self.parse_predicate()?);
+            match self.peek() {
+                Some(Token::Comma) => {
+                    self.next();
+                }
+                _ => break,
+            }
+        }
+        Ok(operands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attrs(board: &str, nightly: bool, dry_run: bool, location: &str) -> TargetAttrs {
+        TargetAttrs {
+            board: board.to_string(),
+            nightly,
+            dry_run,
+            location: location.to_string(),
+        }
+    }
+
+    fn eval(input: &str, attrs: &TargetAttrs) -> bool {
+        Predicate::parse(input).unwrap().eval(attrs).unwrap()
+    }
+
+    #[test]
+    fn parses_eq_and_flag() {
+        assert_eq!(
+            Predicate::parse("board = \"zcu104\"").unwrap(),
+            Predicate::Eq("board".to_string(), "zcu104".to_string())
+        );
+        assert_eq!(
+            Predicate::parse("nightly").unwrap(),
+            Predicate::Flag("nightly".to_string())
+        );
+    }
+
+    #[test]
+    fn eval_not_all_any() {
+        let a = attrs("vck190", false, true, "kir");
+        assert!(eval("not(nightly)", &a));
+        assert!(!eval("not(dry_run)", &a));
+        assert!(eval("all(board = \"vck190\", dry_run)", &a));
+        assert!(!eval("all(board = \"vck190\", nightly)", &a));
+        assert!(eval("any(nightly, dry_run)", &a));
+        assert!(!eval("any(nightly, board = \"zcu104\")", &a));
+    }
+
+    #[test]
+    fn malformed_predicates_error() {
+        assert!(Predicate::parse("all(board = \"zcu104\"").is_err()); // unterminated group
+        assert!(Predicate::parse("board = ").is_err()); // missing string literal
+        assert!(Predicate::parse("not(nightly, dry_run)").is_err()); // not() is unary
+        assert!(Predicate::parse("\"unterminated").is_err()); // unterminated string
+        assert!(Predicate::parse("board ? \"x\"").is_err()); // unexpected character
+    }
+
+    #[test]
+    fn unknown_keys_and_flags_error() {
+        let a = attrs("zcu104", false, false, "kir");
+        assert!(Predicate::parse("arch = \"x\"").unwrap().eval(&a).is_err());
+        assert!(Predicate::parse("debug").unwrap().eval(&a).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! Acquisition of the GitHub App RSA PEM.
+//!
+//! Historically the key was a plaintext PEM on disk, which forces provisioning
+//! workflows to check in or mount an unencrypted signing key. This module keeps
+//! the same `key_path` flag but treats it as a pluggable source: GPG- or
+//! age-encrypted files are decrypted in memory, and `gcpsm://`/`vault://` URIs
+//! fetch the PEM from a secret manager at runtime. Every source returns the raw
+//! PEM bytes, which flow unchanged into `EncodingKey::from_rsa_pem`.
+
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+
+/// Secrets needed to unlock an encrypted key source, from flags or the
+/// environment.
+#[derive(Debug, Clone, Default)]
+pub struct KeyOptions {
+    /// Passphrase for a GPG-encrypted PEM.
+    pub passphrase: Option<String>,
+    /// Path to an age identity file for an age-encrypted PEM.
+    pub age_identity: Option<String>,
+}
+
+impl KeyOptions {
+    /// Resolve options from the command-line flags, falling back to the
+    /// `CRED_TOOL_KEY_PASSPHRASE`/`CRED_TOOL_AGE_IDENTITY` environment
+    /// variables.
+    pub fn resolve(passphrase: Option<String>, age_identity: Option<String>) -> Self {
+        Self {
+            passphrase: passphrase.or_else(|| std::env::var("CRED_TOOL_KEY_PASSPHRASE").ok()),
+            age_identity: age_identity.or_else(|| std::env::var("CRED_TOOL_AGE_IDENTITY").ok()),
+        }
+    }
+}
+
+/// Acquire the PEM bytes named by `key_path`, decrypting or fetching as the
+/// source requires.
+pub fn load(key_path: &str, opts: &KeyOptions) -> Result<Vec<u8>> {
+    if let Some(resource) = key_path.strip_prefix("gcpsm://") {
+        fetch_gcp_secret(resource)
+    } else if let Some(resource) = key_path.strip_prefix("vault://") {
+        fetch_vault_secret(resource)
+    } else if has_extension(key_path, &["gpg", "asc", "pgp"]) {
+        decrypt_gpg(key_path, opts)
+    } else if has_extension(key_path, &["age"]) {
+        decrypt_age(key_path, opts)
+    } else {
+        std::fs::read(key_path).with_context(|| format!("reading key from {key_path}"))
+    }
+}
+
+fn has_extension(path: &str, extensions: &[&str]) -> bool {
+    extensions.iter().any(|ext| {
+        path.rsplit('.')
+            .next()
+            .is_some_and(|found| found.eq_ignore_ascii_case(ext))
+    })
+}
+
+/// Run `command`, returning its stdout on success and surfacing stderr on
+/// failure.
+fn capture(mut command: Command, what: &str) -> Result<Vec<u8>> {
+    let output = command
+        .output()
+        .with_context(|| format!("spawning {what}"))?;
+    if !output.status.success() {
+        bail!(
+            "{what} failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
+
+fn decrypt_gpg(path: &str, opts: &KeyOptions) -> Result<Vec<u8>> {
+    let mut command = Command::new("gpg");
+    command.arg("--batch").arg("--quiet").arg("--decrypt");
+    if let Some(passphrase) = &opts.passphrase {
+        command
+            .arg("--pinentry-mode")
+            .arg("loopback")
+            .arg("--passphrase")
+            .arg(passphrase);
+    }
+    command.arg(path);
+    capture(command, "gpg --decrypt")
+}
+
+fn decrypt_age(path: &str, opts: &KeyOptions) -> Result<Vec<u8>> {
+    let identity = opts.age_identity.as_deref().context(
+        "age-encrypted key requires an identity; pass --age-identity or set CRED_TOOL_AGE_IDENTITY",
+    )?;
+    let mut command = Command::new("age");
+    command
+        .arg("--decrypt")
+        .arg("--identity")
+        .arg(identity)
+        .arg(path);
+    capture(command, "age --decrypt")
+}
+
+/// `gcpsm://projects/<p>/secrets/<s>[/versions/<v>]`
+fn fetch_gcp_secret(resource: &str) -> Result<Vec<u8>> {
+    let parts: Vec<&str> = resource.split('/').collect();
+    let project = segment(&parts, "projects")
+        .context("gcpsm URI must contain projects/<project>")?;
+    let secret =
+        segment(&parts, "secrets").context("gcpsm URI must contain secrets/<secret>")?;
+    let version = segment(&parts, "versions").unwrap_or("latest");
+
+    let mut command = Command::new("gcloud");
+    command
+        .arg("secrets")
+        .arg("versions")
+        .arg("access")
+        .arg(version)
+        .arg(format!("--secret={secret}"))
+        .arg(format!("--project={project}"));
+    capture(command, "gcloud secrets versions access")
+}
+
+/// `vault://<path>[#<field>]`, defaulting to the `value` field.
+fn fetch_vault_secret(resource: &str) -> Result<Vec<u8>> {
+    let (path, field) = match resource.split_once('#') {
+        Some((path, field)) => (path, field),
+        None => (resource, "value"),
+    };
+
+    let mut command = Command::new("vault");
+    command
+        .arg("kv")
+        .arg("get")
+        .arg(format!("-field={field}"))
+        .arg(path);
+    capture(command, "vault kv get")
+}
+
+/// Return the path segment following `key` in a `key/value/...` sequence.
+fn segment<'a>(parts: &[&'a str], key: &str) -> Option<&'a str> {
+    parts
+        .iter()
+        .position(|segment| *segment == key)
+        .and_then(|index| parts.get(index + 1).copied())
+}
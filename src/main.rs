@@ -1,8 +1,22 @@
+use std::path::PathBuf;
+
 use anyhow::Result;
 use chrono::prelude::*;
 use clap::Parser;
 use rand::Rng;
 
+mod audit;
+mod cleanup;
+mod config;
+mod keysource;
+mod retry;
+
+use audit::{AuditRecord, AuditSink};
+use cleanup::CleanupArgs;
+use config::{Config, TargetAttrs};
+use keysource::KeyOptions;
+use retry::RetryConfig;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Stage {
     Carl,
@@ -10,6 +24,17 @@ enum Stage {
     Prod,
 }
 
+impl Stage {
+    /// Lowercase key used to look this stage up in the [`Config`] tables.
+    fn config_key(&self) -> &'static str {
+        match self {
+            Stage::Carl => "carl",
+            Stage::Staging => "staging",
+            Stage::Prod => "prod",
+        }
+    }
+}
+
 impl std::str::FromStr for Stage {
     type Err = String;
 
@@ -33,6 +58,30 @@ enum FpgaTarget {
     Vck190,
 }
 
+impl FpgaTarget {
+    /// Lowercase key used to look this board up in the [`Config`] tables.
+    fn config_key(&self) -> &'static str {
+        match self {
+            FpgaTarget::Zcu104 => "zcu104",
+            FpgaTarget::Zcu104Nightly => "zcu104-nightly",
+            FpgaTarget::Vck190 => "vck190",
+        }
+    }
+
+    /// Board family, shared by nightly and non-nightly variants.
+    fn board_family(&self) -> &'static str {
+        match self {
+            FpgaTarget::Zcu104 | FpgaTarget::Zcu104Nightly => "zcu104",
+            FpgaTarget::Vck190 => "vck190",
+        }
+    }
+
+    /// Whether this is a nightly variant of its board family.
+    fn is_nightly(&self) -> bool {
+        matches!(self, FpgaTarget::Zcu104Nightly)
+    }
+}
+
 impl std::str::FromStr for FpgaTarget {
     type Err = String;
 
@@ -53,21 +102,10 @@ impl std::str::FromStr for FpgaTarget {
 struct RunnerLabels(Vec<String>);
 
 impl RunnerLabels {
-    fn new(value: FpgaTarget, dry_run: bool) -> Self {
-        let postfix = if dry_run { "-staging" } else { "" };
-        let inner = match value {
-            FpgaTarget::Zcu104 => vec!["caliptra-fpga".to_string()],
-            FpgaTarget::Zcu104Nightly => {
-                vec![
-                    "caliptra-fpga".to_string(),
-                    "caliptra-fpga-nightly".to_string(),
-                ]
-            }
-            FpgaTarget::Vck190 => {
-                vec![format!("vck190{}", postfix)]
-            }
-        };
-        Self(inner)
+    /// Resolve the label set for a board by evaluating the config's label rules
+    /// against `attrs`. Errors if no rule matches.
+    fn new(config: &Config, attrs: &TargetAttrs) -> Result<Self> {
+        Ok(Self(config.resolve_labels(attrs)?))
     }
 }
 
@@ -98,23 +136,117 @@ impl RunnerName {
             format!("{board_type}-{location}-{identifier}-{rand_postfix}-{current_date}",);
         Self(runner_name)
     }
+
+    /// Recognise a runner name produced by this tool and return the date
+    /// embedded in its `%Y-%m-%d` suffix. Returns `None` for names that do not
+    /// match the `{board}-{location}-{identifier}-{hex}-{date}` convention, so
+    /// cleanup never touches runners registered by other means.
+    fn parse_embedded_date(name: &str) -> Option<NaiveDate> {
+        let parts: Vec<&str> = name.split('-').collect();
+        // board + location + identifier + hex + 3 date components.
+        if parts.len() < 7 {
+            return None;
+        }
+        let n = parts.len();
+        let date_str = format!("{}-{}-{}", parts[n - 3], parts[n - 2], parts[n - 1]);
+        let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d").ok()?;
+
+        let rand_postfix = parts[n - 4];
+        if rand_postfix.len() == 16 && rand_postfix.chars().all(|c| c.is_ascii_hexdigit()) {
+            Some(date)
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// Issue a JIT runner config for an FPGA board.
+    Provision(ProvisionArgs),
+    /// Remove stale, offline runner registrations left by crashed boards.
+    Cleanup(CleanupArgs),
+}
+
+/// GitHub App identity shared by every subcommand.
+#[derive(clap::Args, Debug)]
+pub struct AuthArgs {
+    /// Deployment stage to provision for. `carl` and `prod` resolve from the
+    /// compiled-in defaults; `staging` has no default identity and requires a
+    /// `--config` that defines a `[stages.staging]` table.
     #[clap(value_enum, short, long, value_name = "STAGE")]
     stage: Stage,
+    /// Path to the GitHub App RSA PEM. Overrides the `key_path` in the config.
+    #[clap(short, long, value_name = "KEY_PATH")]
+    key_path: Option<String>,
+    /// Optional TOML config file defining stages and targets. Defaults to the
+    /// compiled-in values when omitted.
+    #[clap(short, long, value_name = "CONFIG")]
+    config: Option<PathBuf>,
+    /// Passphrase for a GPG-encrypted key. Falls back to
+    /// `CRED_TOOL_KEY_PASSPHRASE`.
+    #[clap(long, value_name = "PASSPHRASE")]
+    key_passphrase: Option<String>,
+    /// Identity file for an age-encrypted key. Falls back to
+    /// `CRED_TOOL_AGE_IDENTITY`.
+    #[clap(long, value_name = "IDENTITY")]
+    age_identity: Option<String>,
+}
+
+impl AuthArgs {
+    /// Load the config file if one was supplied, otherwise the defaults.
+    fn load_config(&self) -> Result<Config> {
+        match &self.config {
+            Some(path) => Config::load(path),
+            None => Ok(Config::default()),
+        }
+    }
+
+    /// Resolve the GitHub App identity for this stage.
+    fn resolve_info(&self, config: &Config) -> Result<CaliptraCiInfo> {
+        let key_options =
+            KeyOptions::resolve(self.key_passphrase.clone(), self.age_identity.clone());
+        CaliptraCiInfo::resolve(config, self.stage, self.key_path.clone(), key_options)
+    }
+
+    /// Build an authenticated [`OctocrabWrapper`] for this stage.
+    fn octocrab(&self, config: &Config) -> Result<OctocrabWrapper> {
+        OctocrabWrapper::new(&self.resolve_info(config)?)
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct ProvisionArgs {
+    #[clap(flatten)]
+    auth: AuthArgs,
     #[clap(value_enum, short, long, value_name = "FPGA_TARGET")]
     fpga_target: FpgaTarget,
     #[clap(short = 'i', long, value_name = "FPGA_IDENTIFIER")]
     fpga_identifier: String,
     #[clap(short = 'l', long, value_name = "LOCATION")]
     location: String,
-    #[clap(short, long, value_name = "KEY_PATH")]
-    key_path: String,
     #[clap(short, long)]
     dry_run: bool,
+    /// Maximum number of retries for the JIT token request after the first
+    /// attempt.
+    #[clap(long, value_name = "MAX_RETRIES", default_value_t = 3)]
+    max_retries: u32,
+    /// Overall deadline, in seconds, covering every attempt and backoff.
+    #[clap(long, value_name = "TIMEOUT_SECS", default_value_t = 60)]
+    timeout: u64,
+    /// Append a newline-delimited JSON audit record to this file.
+    #[clap(long, value_name = "AUDIT_LOG")]
+    audit_log: Option<PathBuf>,
+    /// Also emit the JSON audit record to stderr.
+    #[clap(long)]
+    json: bool,
 }
 
 struct CaliptraCiInfo {
@@ -122,31 +254,34 @@ struct CaliptraCiInfo {
     github_installation_id: u64,
     github_org_name: String,
     key_path: String,
+    key_options: KeyOptions,
 }
 
-impl From<Args> for CaliptraCiInfo {
-    fn from(value: Args) -> Self {
-        match value.stage {
-            Stage::Carl => {
-                // Set environment variables for carl
-                CaliptraCiInfo {
-                    github_app_id: 1160975,
-                    github_installation_id: 61798278,
-                    github_org_name: "clundin25-testorg".to_string(),
-                    key_path: value.key_path,
-                }
-            }
-            Stage::Staging => {
-                // TODO: Set environment variables for staging
-                todo!("TODO: Set environment variables for staging");
-            }
-            Stage::Prod => CaliptraCiInfo {
-                github_app_id: 379559,
-                github_installation_id: 40993215,
-                github_org_name: "chipsalliance".to_string(),
-                key_path: value.key_path,
-            },
-        }
+impl CaliptraCiInfo {
+    /// Resolve the GitHub App identity for `stage` from `config`, using
+    /// `key_path_override` when the caller passed `--key-path`.
+    fn resolve(
+        config: &Config,
+        stage: Stage,
+        key_path_override: Option<String>,
+        key_options: KeyOptions,
+    ) -> Result<Self> {
+        let stage_config = config.stage(stage)?;
+        let key_path = key_path_override
+            .or_else(|| stage_config.key_path.clone())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "no key path provided; pass --key-path or set key_path for stage '{}'",
+                    stage.config_key()
+                )
+            })?;
+        Ok(CaliptraCiInfo {
+            github_app_id: stage_config.github_app_id,
+            github_installation_id: stage_config.github_installation_id,
+            github_org_name: stage_config.github_org_name.clone(),
+            key_path,
+            key_options,
+        })
     }
 }
 
@@ -158,7 +293,8 @@ struct OctocrabWrapper {
 impl OctocrabWrapper {
     fn new(info: &CaliptraCiInfo) -> Result<Self> {
         let github_org_name = info.github_org_name.clone();
-        let key = jsonwebtoken::EncodingKey::from_rsa_pem(&std::fs::read(info.key_path.clone())?)?;
+        let pem = keysource::load(&info.key_path, &info.key_options)?;
+        let key = jsonwebtoken::EncodingKey::from_rsa_pem(&pem)?;
 
         let octocrab = octocrab::Octocrab::builder()
             .app(info.github_app_id.into(), key)
@@ -172,36 +308,134 @@ impl OctocrabWrapper {
         })
     }
 
-    async fn runner_jit_token(&self, name: RunnerName, labels: RunnerLabels) -> Result<String> {
-        // For Caliptra we only use one runner group.
-        let default_runner_group = 1;
+    async fn runner_jit_token(
+        &self,
+        name: RunnerName,
+        labels: RunnerLabels,
+        runner_group: u64,
+        retry: &RetryConfig,
+    ) -> Result<String> {
+        let name = name.0;
+        let labels = labels.0;
 
-        let token = self
-            .octocrab
-            .actions()
-            .create_org_jit_runner_config(
-                self.github_org_name.clone(),
-                name.0,
-                default_runner_group.into(),
-                labels.0,
-            )
-            .send()
-            .await?;
+        let token = retry::run_with_retry(retry, move || {
+            let name = name.clone();
+            let labels = labels.clone();
+            async move {
+                self.octocrab
+                    .actions()
+                    .create_org_jit_runner_config(
+                        self.github_org_name.clone(),
+                        name,
+                        runner_group.into(),
+                        labels,
+                    )
+                    .send()
+                    .await
+            }
+        })
+        .await?;
 
         Ok(token.encoded_jit_config)
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_embedded_date_accepts_our_names() {
+        let date = RunnerName::parse_embedded_date(
+            "caliptra-fpga-kir-id-0123456789ABCDEF-2026-07-25",
+        )
+        .expect("our own runner name should parse");
+        assert_eq!(date, NaiveDate::from_ymd_opt(2026, 7, 25).unwrap());
+    }
+
+    #[test]
+    fn parse_embedded_date_rejects_foreign_names() {
+        // A runner registered by other means (no 16-hex postfix, no date).
+        assert!(RunnerName::parse_embedded_date("self-hosted-linux-x64").is_none());
+        // Right shape but the "postfix" is not 16 hex digits.
+        assert!(RunnerName::parse_embedded_date(
+            "caliptra-fpga-kir-id-notarealpostfix-2026-07-25"
+        )
+        .is_none());
+        // Right postfix but the trailing components are not a valid date.
+        assert!(RunnerName::parse_embedded_date(
+            "caliptra-fpga-kir-id-0123456789ABCDEF-2026-13-40"
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn parse_embedded_date_round_trips_generated_name() {
+        let name = RunnerName::new(FpgaTarget::Zcu104, "id", "kir");
+        assert!(RunnerName::parse_embedded_date(&name.0).is_some());
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
-    eprintln!("Running for stage: {:?}", args.stage);
 
+    match args.command {
+        Command::Provision(args) => provision(args).await,
+        Command::Cleanup(args) => cleanup::run(args).await,
+    }
+}
+
+async fn provision(args: ProvisionArgs) -> Result<()> {
+    eprintln!("Running for stage: {:?}", args.auth.stage);
+
+    let config = args.auth.load_config()?;
+
+    let target = config.target(args.fpga_target)?;
+    let attrs = TargetAttrs {
+        board: args.fpga_target.board_family().to_string(),
+        nightly: args.fpga_target.is_nightly(),
+        dry_run: args.dry_run,
+        location: args.location.clone(),
+    };
     let name = RunnerName::new(args.fpga_target, &args.fpga_identifier, &args.location);
-    let labels = RunnerLabels::new(args.fpga_target, args.dry_run);
-    let github = OctocrabWrapper::new(&args.into())?;
+    let labels = RunnerLabels::new(&config, &attrs)?;
+    let runner_group = target.runner_group;
+
+    let info = args.auth.resolve_info(&config)?;
+    let github = OctocrabWrapper::new(&info)?;
+
+    let retry = RetryConfig {
+        max_retries: args.max_retries,
+        timeout: std::time::Duration::from_secs(args.timeout),
+    };
+
+    // Capture the identifying fields before `name`/`labels` are consumed by the
+    // request so they can be recorded regardless of outcome.
+    let runner_name = name.0.clone();
+    let runner_labels = labels.0.clone();
+    let audit = AuditSink::new(args.audit_log.clone(), args.json);
+
+    let result = github
+        .runner_jit_token(name, labels, runner_group, &retry)
+        .await;
+
+    let record = AuditRecord {
+        timestamp: Local::now().to_rfc3339(),
+        runner_name: &runner_name,
+        labels: &runner_labels,
+        stage: args.auth.stage.config_key(),
+        org: &info.github_org_name,
+        github_app_id: info.github_app_id,
+        installation_id: info.github_installation_id,
+        success: result.is_ok(),
+        error: result.as_ref().err().map(|e| e.to_string()),
+    };
+    if let Err(e) = audit.emit(&record) {
+        eprintln!("Failed to write audit record: {e:}");
+    }
 
-    match github.runner_jit_token(name, labels).await {
+    match result {
         Ok(jit_config) => {
             println!("{jit_config}");
             std::process::exit(0);
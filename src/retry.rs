@@ -0,0 +1,133 @@
+//! Retry-with-backoff helper for the GitHub API calls.
+//!
+//! CI systems retry transient `runner_system_failure`/`api_failure` conditions
+//! and terminate slow operations; this mirrors that so the tool is safe to run
+//! from unattended FPGA provisioning scripts. Retries happen only on
+//! conditions that are plausibly transient (HTTP 5xx, a 429 or the
+//! secondary-rate-limit 403, and connection/timeout errors), while the ordinary
+//! 4xx authentication/permission failures fail fast. The whole attempt sequence
+//! is bounded by an overall deadline.
+//!
+//! GitHub delivers its rate-limit hint in the `Retry-After` *response header*,
+//! but octocrab does not surface response headers on [`octocrab::Error`], so we
+//! cannot honour it here; retries fall back to local exponential backoff with
+//! jitter instead.
+
+use std::future::Future;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use rand::Rng;
+
+/// Tunables for [`run_with_retry`], populated from the command-line flags.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryConfig {
+    /// Maximum number of retries after the initial attempt.
+    pub max_retries: u32,
+    /// Hard deadline covering every attempt and backoff sleep.
+    pub timeout: Duration,
+}
+
+/// Base delay for the first backoff; doubled on each subsequent retry.
+const BASE_DELAY: Duration = Duration::from_millis(500);
+/// Upper bound on a single backoff sleep before jitter.
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// What to do with a failed attempt.
+enum Decision {
+    /// The error is not retryable; surface it immediately.
+    Fail,
+    /// Retry after an exponential-backoff sleep.
+    Retry,
+}
+
+/// Run `attempt` under a retry policy, bounded by `config.timeout`.
+///
+/// `attempt` is invoked afresh for every try, so it must rebuild any request
+/// state it needs. Retryable failures back off exponentially with jitter;
+/// non-retryable failures return immediately.
+pub async fn run_with_retry<F, Fut, T>(config: &RetryConfig, attempt: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = octocrab::Result<T>>,
+{
+    tokio::time::timeout(config.timeout, retry_loop(config, attempt))
+        .await
+        .map_err(|_| {
+            anyhow!(
+                "operation timed out after {} seconds",
+                config.timeout.as_secs()
+            )
+        })?
+}
+
+async fn retry_loop<F, Fut, T>(config: &RetryConfig, mut attempt: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = octocrab::Result<T>>,
+{
+    let mut delay = BASE_DELAY;
+    for attempt_no in 0..=config.max_retries {
+        match attempt().await {
+            Ok(value) => return Ok(value),
+            Err(err) => match classify(&err) {
+                Decision::Fail => return Err(err.into()),
+                Decision::Retry if attempt_no == config.max_retries => {
+                    return Err(anyhow::Error::new(err)
+                        .context(format!("giving up after {} retries", config.max_retries)));
+                }
+                Decision::Retry => {
+                    let sleep = jitter(delay);
+                    eprintln!(
+                        "Attempt {} failed ({err}); retrying in {:?}",
+                        attempt_no + 1,
+                        sleep
+                    );
+                    tokio::time::sleep(sleep).await;
+                    delay = (delay * 2).min(MAX_DELAY);
+                }
+            },
+        }
+    }
+    unreachable!("retry loop always returns within the bounded range")
+}
+
+/// Apply full jitter to `delay`, i.e. a uniformly random value in `[0, delay]`.
+fn jitter(delay: Duration) -> Duration {
+    let mut rng = rand::rng();
+    delay.mul_f64(rng.random::<f64>())
+}
+
+/// Decide whether an octocrab error is worth retrying.
+fn classify(err: &octocrab::Error) -> Decision {
+    match err {
+        octocrab::Error::GitHub { source, .. } => {
+            let status = source.status_code;
+            if status.is_server_error() || status == http::StatusCode::TOO_MANY_REQUESTS {
+                Decision::Retry
+            } else if status == http::StatusCode::FORBIDDEN && is_secondary_rate_limit(source) {
+                // GitHub reports the secondary rate limit as a 403 rather than a
+                // 429. Only those are transient; a plain 403 is a permanent
+                // auth/permission failure (e.g. the App lacks org access) and
+                // must fail fast rather than burn the whole retry budget.
+                Decision::Retry
+            } else {
+                // 4xx auth/validation errors will not succeed on retry.
+                Decision::Fail
+            }
+        }
+        // Transport-level failures (connection reset, DNS, timeouts) are
+        // transient and safe to retry.
+        octocrab::Error::Hyper { .. }
+        | octocrab::Error::Service { .. }
+        | octocrab::Error::Http { .. } => Decision::Retry,
+        _ => Decision::Fail,
+    }
+}
+
+/// Whether a `403 FORBIDDEN` carries a secondary-rate-limit signal, as opposed
+/// to an ordinary permission denial. GitHub phrases these as "You have exceeded
+/// a secondary rate limit" in the error message body.
+fn is_secondary_rate_limit(source: &octocrab::GitHubError) -> bool {
+    source.message.to_lowercase().contains("secondary rate limit")
+}